@@ -28,8 +28,11 @@ pub enum AminoAcid {
 }
 
 impl From<char> for AminoAcid {
+  /// NCBI FASTA downloads mark soft-masked (repeat) regions with lowercase
+  /// letters, so we uppercase before matching instead of letting those
+  /// residues fall through to `Unknown`...
   fn from(c: char) -> Self {
-    match c {
+    match c.to_ascii_uppercase() {
       'A' => AminoAcid::Alanine,
       'R' => AminoAcid::Arginine,
       'N' => AminoAcid::Asparagine,
@@ -58,3 +61,29 @@ impl From<char> for AminoAcid {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn uppercase_residues_map_to_their_variant() {
+    assert_eq!(AminoAcid::from('A'), AminoAcid::Alanine);
+    assert_eq!(AminoAcid::from('X'), AminoAcid::Unknown);
+    assert_eq!(AminoAcid::from('*'), AminoAcid::Stop);
+  }
+
+  #[test]
+  fn lowercase_residues_are_treated_like_their_uppercase_counterpart() {
+    // soft-masked FASTA marks repeat regions with lowercase residues; these
+    // used to silently fall through to `Unknown`...
+    assert_eq!(AminoAcid::from('a'), AminoAcid::Alanine);
+    assert_eq!(AminoAcid::from('r'), AminoAcid::Arginine);
+    assert_eq!(AminoAcid::from('v'), AminoAcid::Valine);
+  }
+
+  #[test]
+  fn unrecognized_characters_are_unknown() {
+    assert_eq!(AminoAcid::from('?'), AminoAcid::Unknown);
+  }
+}
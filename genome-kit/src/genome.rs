@@ -1,35 +1,34 @@
 use std::ops::{Deref, DerefMut};
 
-use crate::amino::AminoAcid;
-
-/// A genome is a list of amino acids for something like COX3
+/// A genome is a list of residues (amino acids, nucleotides, ...) for
+/// something like COX3
 #[derive(Debug, Clone, PartialEq)]
-pub struct Genome(Vec<AminoAcid>);
+pub struct Genome<R>(Vec<R>);
 
-impl<T, I> From<I> for Genome
+impl<R, T, I> From<I> for Genome<R>
 where
-  T: Into<AminoAcid>,
+  T: Into<R>,
   I: IntoIterator<Item = T>,
 {
   /// We should be able to get a genome from any list of things treatable as
-  /// amino acids...
+  /// residues...
   fn from(iter: I) -> Self {
     Self(iter.into_iter().map(Into::into).collect())
   }
 }
 
-// our `Genome` struct is a sort of smart-pointer wrapper for the actual amino
-// acids it contains...
+// our `Genome` struct is a sort of smart-pointer wrapper for the actual
+// residues it contains...
 
-impl Deref for Genome {
-  type Target = Vec<AminoAcid>;
+impl<R> Deref for Genome<R> {
+  type Target = Vec<R>;
 
   fn deref(&self) -> &Self::Target {
     &self.0
   }
 }
 
-impl DerefMut for Genome {
+impl<R> DerefMut for Genome<R> {
   fn deref_mut(&mut self) -> &mut Self::Target {
     &mut self.0
   }
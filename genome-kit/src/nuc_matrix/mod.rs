@@ -0,0 +1,34 @@
+use crate::nucleotide::Nucleotide;
+
+pub mod nuc44;
+pub use nuc44::*;
+
+pub(crate) const ROWS: usize = 6;
+pub(crate) const COLS: usize = 6;
+
+/// A trait to abstract over kinds of nucleotide scoring matrices, mirroring
+/// `Blosum` for amino acids...
+pub trait NucMatrix {
+  /// Returns the substitution score for two bases
+  fn score(&self, a: Nucleotide, b: Nucleotide) -> i32;
+}
+
+// all tables share the same size and positions, so we provide a generic way
+// to access a table index given some base...
+#[inline]
+pub(crate) const fn base_to_index(base: Nucleotide) -> usize {
+  match base {
+    Nucleotide::Adenine => 0,
+    Nucleotide::Cytosine => 1,
+    Nucleotide::Guanine => 2,
+    Nucleotide::Thymine => 3,
+    Nucleotide::Unknown => 4,
+    Nucleotide::Gap => 5,
+  }
+}
+
+#[inline]
+pub(crate) const fn score_for(matrix: &[i32; ROWS * COLS], a: Nucleotide, b: Nucleotide) -> i32 {
+  let (i, j) = (base_to_index(a), base_to_index(b));
+  matrix[i * COLS + j]
+}
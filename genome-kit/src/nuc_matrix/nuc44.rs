@@ -0,0 +1,72 @@
+use crate::nucleotide::Nucleotide;
+
+use super::{score_for, NucMatrix, COLS, ROWS};
+
+/// NCBI's NUC.4.4 matrix, restricted to the four bases plus N and a gap
+/// marker: a simple match/mismatch scheme (+5/-4) with N scoring as a wash
+/// against anything and a gap never contributing a substitution score...
+///
+/// - https://ftp.ncbi.nlm.nih.gov/blast/matrices/NUC.4.4
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Nuc44;
+
+#[rustfmt::skip]
+const TABLE: [i32; ROWS * COLS] = {
+  const MATCH: i32 = 5;
+  const MISMATCH: i32 = -4;
+  [
+  // A         C         G         T         N    -
+     MATCH,    MISMATCH, MISMATCH, MISMATCH, 0,   0, // A
+     MISMATCH, MATCH,    MISMATCH, MISMATCH, 0,   0, // C
+     MISMATCH, MISMATCH, MATCH,    MISMATCH, 0,   0, // G
+     MISMATCH, MISMATCH, MISMATCH, MATCH,    0,   0, // T
+     0,        0,        0,        0,        0,   0, // N
+     0,        0,        0,        0,        0,   0, // -
+  ]
+};
+
+impl NucMatrix for Nuc44 {
+  #[inline]
+  fn score(&self, a: Nucleotide, b: Nucleotide) -> i32 {
+    score_for(&TABLE, a, b)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn identical_bases_score_a_match() {
+    let nuc44 = Nuc44;
+    for base in [
+      Nucleotide::Adenine,
+      Nucleotide::Cytosine,
+      Nucleotide::Guanine,
+      Nucleotide::Thymine,
+    ] {
+      assert_eq!(nuc44.score(base, base), 5);
+    }
+  }
+
+  #[test]
+  fn different_bases_score_a_mismatch() {
+    let nuc44 = Nuc44;
+    assert_eq!(nuc44.score(Nucleotide::Adenine, Nucleotide::Cytosine), -4);
+    assert_eq!(nuc44.score(Nucleotide::Guanine, Nucleotide::Thymine), -4);
+  }
+
+  #[test]
+  fn unknown_base_is_a_wash_against_anything() {
+    let nuc44 = Nuc44;
+    assert_eq!(nuc44.score(Nucleotide::Unknown, Nucleotide::Adenine), 0);
+    assert_eq!(nuc44.score(Nucleotide::Unknown, Nucleotide::Unknown), 0);
+  }
+
+  #[test]
+  fn gap_never_contributes_a_score() {
+    let nuc44 = Nuc44;
+    assert_eq!(nuc44.score(Nucleotide::Gap, Nucleotide::Adenine), 0);
+    assert_eq!(nuc44.score(Nucleotide::Gap, Nucleotide::Gap), 0);
+  }
+}
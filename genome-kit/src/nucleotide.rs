@@ -0,0 +1,56 @@
+/// The four DNA bases, plus N for unknown and a gap marker...
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Nucleotide {
+  Adenine,  // A
+  Cytosine, // C
+  Guanine,  // G
+  Thymine,  // T
+  Unknown,  // N
+  Gap,      // -
+}
+
+impl From<char> for Nucleotide {
+  /// Soft-masked FASTA (common in downloaded genomes) uses lowercase bases to
+  /// mark repeat regions, so we normalize case before matching rather than
+  /// treating masked bases as unknown...
+  fn from(c: char) -> Self {
+    match c.to_ascii_uppercase() {
+      'A' => Nucleotide::Adenine,
+      'C' => Nucleotide::Cytosine,
+      'G' => Nucleotide::Guanine,
+      'T' => Nucleotide::Thymine,
+      '-' => Nucleotide::Gap,
+      _ => Nucleotide::Unknown, // Default case for invalid characters
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn uppercase_bases_map_to_their_variant() {
+    assert_eq!(Nucleotide::from('A'), Nucleotide::Adenine);
+    assert_eq!(Nucleotide::from('C'), Nucleotide::Cytosine);
+    assert_eq!(Nucleotide::from('G'), Nucleotide::Guanine);
+    assert_eq!(Nucleotide::from('T'), Nucleotide::Thymine);
+    assert_eq!(Nucleotide::from('N'), Nucleotide::Unknown);
+    assert_eq!(Nucleotide::from('-'), Nucleotide::Gap);
+  }
+
+  #[test]
+  fn lowercase_bases_are_treated_like_their_uppercase_counterpart() {
+    // soft-masked FASTA marks repeat regions with lowercase bases; these
+    // used to silently fall through to `Unknown`...
+    assert_eq!(Nucleotide::from('a'), Nucleotide::Adenine);
+    assert_eq!(Nucleotide::from('c'), Nucleotide::Cytosine);
+    assert_eq!(Nucleotide::from('g'), Nucleotide::Guanine);
+    assert_eq!(Nucleotide::from('t'), Nucleotide::Thymine);
+  }
+
+  #[test]
+  fn unrecognized_characters_are_unknown() {
+    assert_eq!(Nucleotide::from('Z'), Nucleotide::Unknown);
+  }
+}
@@ -0,0 +1,27 @@
+use crate::amino::AminoAcid;
+use crate::blosum::Blosum;
+use crate::nuc_matrix::NucMatrix;
+use crate::nucleotide::Nucleotide;
+
+/// Common interface over a residue's scoring table, so algorithms like
+/// Needleman-Wunsch can be written once and generic over the residue
+/// alphabet instead of being hard-wired to `AminoAcid`/`Blosum`...
+pub trait ScoringTable<R> {
+  fn score(&self, a: R, b: R) -> i32;
+}
+
+// any `Blosum` table already knows how to score amino acids...
+impl<B: Blosum> ScoringTable<AminoAcid> for B {
+  #[inline]
+  fn score(&self, a: AminoAcid, b: AminoAcid) -> i32 {
+    Blosum::score(self, a, b)
+  }
+}
+
+// any `NucMatrix` table already knows how to score nucleotides...
+impl<N: NucMatrix> ScoringTable<Nucleotide> for N {
+  #[inline]
+  fn score(&self, a: Nucleotide, b: Nucleotide) -> i32 {
+    NucMatrix::score(self, a, b)
+  }
+}
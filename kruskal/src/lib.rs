@@ -5,8 +5,8 @@ use rayon::prelude::*;
 
 use lib_needleman::Needleman;
 
-use lib_genome_kit::blosum::Blosum62;
 use lib_genome_kit::genome::Genome;
+use lib_genome_kit::scoring::ScoringTable;
 
 /// Wrapper for a species index
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -54,17 +54,76 @@ impl Ord for Edge {
   }
 }
 
-/// A similarity-score based binary tree, generic over leaf storage...
+/// A distance-based binary tree, generic over leaf storage...
+///
+/// `similarity` is the negated merge height, derived from a normalized,
+/// unit-ish `distance` (see `normalized_distance`; 0 for identical sequences)
+/// rather than a raw Needleman-Wunsch score, which has no fixed scale and would
+/// swamp `phylogeny`'s `similarity_to_height` clamp for any realistically
+/// similar pair of sequences. `Kruskal` stores `-distance` of the edge that
+/// caused the merge; `Upgma` stores `-(distance / 2)`, the UPGMA node height
+/// for the average-linkage distance between the merged clusters.
+/// `f64` so UPGMA's real-valued heights survive without rounding.
 #[derive(Debug, Clone)]
 pub enum Cluster<T> {
   Leaf(T),
   Node {
     left: Box<Cluster<T>>,
     right: Box<Cluster<T>>,
-    similarity: i32,
+    similarity: f64,
   },
 }
 
+impl<T> Cluster<T> {
+  /// An explicit-stack (not recursive) traversal over every node of the tree,
+  /// yielding both `Leaf`s and `Node`s in pre-order. `descending` picks which
+  /// child is visited first at each `Node`: `true` visits `left` before
+  /// `right`, `false` visits `right` before `left`.
+  #[inline]
+  pub fn iter(&self, descending: bool) -> ClusterIter<'_, T> {
+    ClusterIter {
+      stack: vec![self],
+      descending,
+    }
+  }
+
+  /// Convenience over `iter` that yields only the leaf values, left-to-right.
+  #[inline]
+  pub fn leaves(&self) -> impl Iterator<Item = &T> {
+    self.iter(true).filter_map(|cluster| match cluster {
+      Cluster::Leaf(value) => Some(value),
+      Cluster::Node { .. } => None,
+    })
+  }
+}
+
+/// Iterator returned by `Cluster::iter`, backed by an explicit stack rather
+/// than recursion.
+pub struct ClusterIter<'a, T> {
+  stack: Vec<&'a Cluster<T>>,
+  descending: bool,
+}
+
+impl<'a, T> Iterator for ClusterIter<'a, T> {
+  type Item = &'a Cluster<T>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let node = self.stack.pop()?;
+    if let Cluster::Node { left, right, .. } = node {
+      // push the second-to-visit child first so the first-to-visit child
+      // pops off the stack next
+      if self.descending {
+        self.stack.push(right);
+        self.stack.push(left);
+      } else {
+        self.stack.push(left);
+        self.stack.push(right);
+      }
+    }
+    Some(node)
+  }
+}
+
 /// A simple union–find (disjoint set) implementation using negative parent values to encode set size.
 struct UnionFind {
   parent: Vec<isize>, // root: –size, otherwise: index of parent
@@ -116,16 +175,17 @@ impl UnionFind {
   }
 }
 
-/// A species of animal, has some name and some genome...
+/// A species of animal, has some name and some genome of residue type `R`
+/// (amino acids, nucleotides, ...)...
 #[derive(Debug, Clone, PartialEq)]
-pub struct Species {
+pub struct Species<R> {
   name: String,
-  genome: Genome,
+  genome: Genome<R>,
 }
 
-impl Species {
+impl<R> Species<R> {
   #[inline]
-  pub fn new(name: String, genome: impl Into<Genome>) -> Self {
+  pub fn new(name: String, genome: impl Into<Genome<R>>) -> Self {
     Self {
       name,
       genome: genome.into(),
@@ -141,9 +201,9 @@ trait Partition {
   fn part(self) -> (Self::A, Self::B);
 }
 
-impl Partition for Species {
+impl<R> Partition for Species<R> {
   type A = String;
-  type B = Genome;
+  type B = Genome<R>;
 
   /// We can part a `Species` into its components
   #[inline]
@@ -221,35 +281,33 @@ impl<T> ClusterManager<T> {
   }
 }
 
-pub trait Kruskal {
+/// Clusters a collection of leaves into a dendrogram. Generic over the
+/// residue type `R` the leaves' genomes are made of and the scoring table `S`
+/// used to compute pairwise similarity between them.
+pub trait Kruskal<R, S> {
   type Leaf;
 
   fn cluster(self) -> Option<Cluster<Self::Leaf>>;
 }
 
-impl<T, P> Kruskal for Vec<P>
+impl<T, R, S, P> Kruskal<R, S> for Vec<P>
 where
   T: Clone,
-  P: Partition<A = T, B = Genome>,
+  R: Copy + Sync,
+  S: ScoringTable<R> + Default,
+  P: Partition<A = T, B = Genome<R>>,
 {
   /// A leaf is a species' name
   type Leaf = T;
 
   fn cluster(self) -> Option<Cluster<Self::Leaf>> {
     let (names, genomes) = self.part();
-<<<<<<< HEAD
-    if names.is_empty() || genomes.is_empty() {
-      return None;
-    }
-
-=======
     if names.is_empty() {
       return None;
     }
-    
->>>>>>> 087b68c3ea25b385887ebe86b9dfdf6e9a1d2d6b
+
     let mut manager = ClusterManager::new(create_initial_clusters(names));
-    let mut heap = compute_edges(&genomes);
+    let mut heap = compute_edges::<R, S>(&genomes);
 
     while let Some(edge) = heap.pop() {
       let a = edge.species_a;
@@ -257,10 +315,11 @@ where
       let ca = manager.get(a);
       let cb = manager.get(b);
       if ca != cb {
+        let distance = normalized_distance::<R, S>(edge.similarity, &genomes[a.index()], &genomes[b.index()]);
         let new_cluster = Cluster::Node {
           left: Box::new(manager.clusters[ca.index()].clone()),
           right: Box::new(manager.clusters[cb.index()].clone()),
-          similarity: edge.similarity,
+          similarity: -distance,
         };
         let new_id = manager.add_cluster(new_cluster);
         manager.merge(ca, cb, new_id);
@@ -271,6 +330,110 @@ where
   }
 }
 
+/// Clusters a collection of leaves into a dendrogram using UPGMA
+/// (average-linkage) rather than Kruskal's greedy max-heap merge, producing a
+/// proper ultrametric tree with monotonically increasing merge heights.
+/// Sibling to `Kruskal`, same generics.
+pub trait Upgma<R, S> {
+  type Leaf;
+
+  fn cluster_upgma(self) -> Option<Cluster<Self::Leaf>>;
+}
+
+impl<T, R, S, P> Upgma<R, S> for Vec<P>
+where
+  T: Clone,
+  R: Copy + Sync,
+  S: ScoringTable<R> + Default,
+  P: Partition<A = T, B = Genome<R>>,
+{
+  /// A leaf is a species' name
+  type Leaf = T;
+
+  fn cluster_upgma(self) -> Option<Cluster<Self::Leaf>> {
+    let (names, genomes) = self.part();
+    let n = names.len();
+    if n == 0 {
+      return None;
+    }
+
+    let mut dist = vec![vec![0.0f64; n]; n];
+    for i in 0..n {
+      for j in (i + 1)..n {
+        let similarity = S::needleman_wunsch(&genomes[i], &genomes[j]);
+        let d = normalized_distance::<R, S>(similarity, &genomes[i], &genomes[j]);
+        dist[i][j] = d;
+        dist[j][i] = d;
+      }
+    }
+
+    // one slot per original leaf, reused in place as clusters merge; `active`
+    // tracks which slots still hold a live (not-yet-merged) cluster...
+    let mut clusters: Vec<Option<Cluster<T>>> = create_initial_clusters(names).into_iter().map(Some).collect();
+    let mut sizes = vec![1usize; n];
+    let mut active: Vec<usize> = (0..n).collect();
+
+    while active.len() > 1 {
+      // find the closest pair among the still-active clusters
+      let mut closest = (f64::INFINITY, 0, 0);
+      for (pos, &i) in active.iter().enumerate() {
+        for &j in &active[pos + 1..] {
+          if dist[i][j] < closest.0 {
+            closest = (dist[i][j], i, j);
+          }
+        }
+      }
+      let (d_ij, i, j) = closest;
+      let height = d_ij / 2.0;
+
+      let (size_i, size_j) = (sizes[i], sizes[j]);
+      let new_cluster = Cluster::Node {
+        left: Box::new(clusters[i].take().unwrap()),
+        right: Box::new(clusters[j].take().unwrap()),
+        // negated so `similarity_to_height`-style consumers recover `height`
+        // exactly, with no rounding into a discrete score...
+        similarity: -height,
+      };
+
+      // size-weighted average distance from the merged cluster (stored back
+      // into slot `i`) to every other still-active cluster...
+      for &k in active.iter().filter(|&&k| k != i && k != j) {
+        let d = (size_i as f64 * dist[i][k] + size_j as f64 * dist[j][k]) / (size_i + size_j) as f64;
+        dist[i][k] = d;
+        dist[k][i] = d;
+      }
+
+      clusters[i] = Some(new_cluster);
+      sizes[i] = size_i + size_j;
+      active.retain(|&slot| slot != j);
+    }
+
+    clusters[active[0]].take()
+  }
+}
+
+// a raw Needleman-Wunsch score has no fixed scale (it grows with sequence
+// length and the scoring table in use), so we normalize it into a distance
+// by comparing it against the best score either sequence could possibly
+// achieve (its self-alignment score). identical sequences land at distance
+// 0; sequences that share no similarity beyond what the matrix hands out
+// for free land near distance 1; the shorter/weaker of the two self-scores
+// is used as the denominator since the cross-alignment rarely scores better
+// than either sequence aligned against itself (it can when a residue scores
+// higher against another residue than against itself, e.g. Blosum62's X,
+// and the distance then drops below 0)...
+#[inline]
+fn normalized_distance<R, S>(similarity: i32, a: &Genome<R>, b: &Genome<R>) -> f64
+where
+  R: Copy,
+  S: ScoringTable<R> + Default,
+{
+  let self_a = S::needleman_wunsch(a, a);
+  let self_b = S::needleman_wunsch(b, b);
+  let max_possible = self_a.min(self_b).max(1);
+  1.0 - (similarity as f64 / max_possible as f64)
+}
+
 // initial clusters are just leaves representing all the species names...
 #[inline]
 fn create_initial_clusters<T>(names: Vec<T>) -> Vec<Cluster<T>> {
@@ -280,13 +443,17 @@ fn create_initial_clusters<T>(names: Vec<T>) -> Vec<Cluster<T>> {
 // !!! this is the hottest function in the program !!!
 // we avoid inline so i can profile with perf lol
 #[inline(never)]
-fn compute_edges(genomes: &[Genome]) -> BinaryHeap<Edge> {
+fn compute_edges<R, S>(genomes: &[Genome<R>]) -> BinaryHeap<Edge>
+where
+  R: Copy + Sync,
+  S: ScoringTable<R> + Default,
+{
   let n = genomes.len();
   let edges: Vec<Edge> = (0..n)
     .into_par_iter()
     .flat_map_iter(|i| {
       (i + 1..n).map(move |j| {
-        let score = Blosum62::needleman_wunsch(&genomes[i], &genomes[j]);
+        let score = S::needleman_wunsch(&genomes[i], &genomes[j]);
         Edge {
           species_a: SpeciesId(i),
           species_b: SpeciesId(j),
@@ -379,9 +546,12 @@ mod tests {
   mod kruskal {
     use super::*;
 
+    use lib_genome_kit::amino::AminoAcid;
+    use lib_genome_kit::blosum::Blosum62;
+
     #[test]
     fn cluster() {
-      let species = vec![
+      let species: Vec<Species<AminoAcid>> = vec![
         Species::new("Species A".into(), "ARND".chars()),
         Species::new("Species B".into(), "ARNE".chars()),
         Species::new("Species C".into(), "ARNS".chars()),
@@ -392,11 +562,175 @@ mod tests {
         Species::new("Species H".into(), "ARDS".chars()),
         Species::new("Species I".into(), "RRNS".chars()),
       ];
-      let dendrogram = species.cluster();
+      let dendrogram = Kruskal::<AminoAcid, Blosum62>::cluster(species);
       assert!(dendrogram.is_some());
       // should definitely be a better test, but we were running into ordering
       // concerns while optimizing between vec and binaryheap so im too lazy to
       // add something concrete here
     }
   }
+
+  mod normalized_distance {
+    use super::*;
+
+    use lib_genome_kit::amino::AminoAcid;
+    use lib_genome_kit::blosum::Blosum62;
+
+    #[test]
+    fn identical_sequences_are_distance_zero() {
+      let a = Genome::<AminoAcid>::from("ARNDCQEGHILKMFPSTWYV".chars());
+      let score = Blosum62::needleman_wunsch(&a, &a);
+      assert_eq!(normalized_distance::<AminoAcid, Blosum62>(score, &a, &a), 0.0);
+    }
+
+    #[test]
+    fn realistic_similarity_does_not_collapse_to_a_clamped_zero_height() {
+      // a raw NW score for two highly-similar real sequences is deeply
+      // positive (Blosum62 matches score +4 to +11), so negating it directly
+      // used to produce a hugely negative "height" that `similarity_to_height`
+      // clamped to 0 for almost every realistic merge; normalizing against
+      // each sequence's own self-alignment score keeps the distance (and thus
+      // the resulting height) meaningfully above zero instead.
+      let a = Genome::<AminoAcid>::from("ARNDCQEGHILKMFPSTWYV".chars());
+      let b = Genome::<AminoAcid>::from("ARNDCQEGHILKMFPSTWYA".chars());
+      let score = Blosum62::needleman_wunsch(&a, &b);
+      let distance = normalized_distance::<AminoAcid, Blosum62>(score, &a, &b);
+      assert!(distance > 0.0);
+    }
+  }
+
+  mod upgma {
+    use super::*;
+
+    use lib_genome_kit::amino::AminoAcid;
+    use lib_genome_kit::blosum::Blosum62;
+
+    #[test]
+    fn cluster_upgma_builds_a_single_tree() {
+      let species: Vec<Species<AminoAcid>> = vec![
+        Species::new("Species A".into(), "ARND".chars()),
+        Species::new("Species B".into(), "ARNE".chars()),
+        Species::new("Species C".into(), "ARNS".chars()),
+        Species::new("Species D".into(), "RRDD".chars()),
+      ];
+      let dendrogram = Upgma::<AminoAcid, Blosum62>::cluster_upgma(species);
+      assert!(dendrogram.is_some());
+      // every original leaf should still be present somewhere in the tree
+      let leaves: Vec<&str> = dendrogram.unwrap().leaves().map(|s| s.as_str()).collect();
+      assert_eq!(leaves.len(), 4);
+    }
+
+    #[test]
+    fn cluster_upgma_single_species_is_just_a_leaf() {
+      let species: Vec<Species<AminoAcid>> = vec![Species::new("Species A".into(), "ARND".chars())];
+      let dendrogram = Upgma::<AminoAcid, Blosum62>::cluster_upgma(species);
+      assert!(matches!(dendrogram, Some(Cluster::Leaf(_))));
+    }
+
+    #[test]
+    fn cluster_upgma_empty_input_is_none() {
+      let species: Vec<Species<AminoAcid>> = vec![];
+      let dendrogram = Upgma::<AminoAcid, Blosum62>::cluster_upgma(species);
+      assert!(dendrogram.is_none());
+    }
+
+    #[test]
+    fn cluster_upgma_preserves_fractional_height() {
+      // the two closest species merge first, at height d/2 where d is their
+      // normalized NW distance; normalization divides by a self-alignment
+      // score, so the result is essentially always fractional, which used to
+      // get rounded away when `similarity` was an `i32` field...
+      let a = Genome::<AminoAcid>::from("ARND".chars());
+      let b = Genome::<AminoAcid>::from("ARNE".chars());
+      let score = Blosum62::needleman_wunsch(&a, &b);
+      let expected_distance = normalized_distance::<AminoAcid, Blosum62>(score, &a, &b);
+
+      let species: Vec<Species<AminoAcid>> = vec![
+        Species::new("Species A".into(), "ARND".chars()),
+        Species::new("Species B".into(), "ARNE".chars()),
+      ];
+      let dendrogram = Upgma::<AminoAcid, Blosum62>::cluster_upgma(species).unwrap();
+      match dendrogram {
+        Cluster::Node { similarity, .. } => {
+          assert_eq!(similarity, -(expected_distance / 2.0));
+        }
+        Cluster::Leaf(_) => panic!("expected a merged node, got a single leaf"),
+      }
+    }
+  }
+
+  mod nucleotide {
+    use super::*;
+
+    use lib_genome_kit::nuc_matrix::Nuc44;
+    use lib_genome_kit::nucleotide::Nucleotide;
+
+    #[test]
+    fn cluster_builds_a_tree_over_dna_sequences() {
+      let species: Vec<Species<Nucleotide>> = vec![
+        Species::new("Species A".into(), "ACGTACGT".chars()),
+        Species::new("Species B".into(), "ACGTACGA".chars()),
+        Species::new("Species C".into(), "TTTTTTTT".chars()),
+      ];
+      let dendrogram = Kruskal::<Nucleotide, Nuc44>::cluster(species);
+      assert!(dendrogram.is_some());
+      let leaves: Vec<&str> = dendrogram.unwrap().leaves().map(|s| s.as_str()).collect();
+      assert_eq!(leaves.len(), 3);
+    }
+
+    #[test]
+    fn cluster_upgma_builds_a_tree_over_dna_sequences() {
+      let species: Vec<Species<Nucleotide>> = vec![
+        Species::new("Species A".into(), "ACGTACGT".chars()),
+        Species::new("Species B".into(), "ACGTACGA".chars()),
+        Species::new("Species C".into(), "TTTTTTTT".chars()),
+      ];
+      let dendrogram = Upgma::<Nucleotide, Nuc44>::cluster_upgma(species);
+      assert!(dendrogram.is_some());
+      let leaves: Vec<&str> = dendrogram.unwrap().leaves().map(|s| s.as_str()).collect();
+      assert_eq!(leaves.len(), 3);
+    }
+  }
+
+  mod cluster_iter {
+    use super::*;
+
+    fn sample_tree() -> Cluster<&'static str> {
+      Cluster::Node {
+        left: Box::new(Cluster::Leaf("a")),
+        right: Box::new(Cluster::Node {
+          left: Box::new(Cluster::Leaf("b")),
+          right: Box::new(Cluster::Leaf("c")),
+          similarity: 2.0,
+        }),
+        similarity: 1.0,
+      }
+    }
+
+    #[test]
+    fn leaves_are_visited_left_to_right() {
+      let tree = sample_tree();
+      let leaves: Vec<&&str> = tree.leaves().collect();
+      assert_eq!(leaves, vec![&"a", &"b", &"c"]);
+    }
+
+    #[test]
+    fn descending_false_visits_right_before_left() {
+      let tree = sample_tree();
+      let leaves: Vec<&str> = tree
+        .iter(false)
+        .filter_map(|cluster| match cluster {
+          Cluster::Leaf(value) => Some(*value),
+          Cluster::Node { .. } => None,
+        })
+        .collect();
+      assert_eq!(leaves, vec!["c", "b", "a"]);
+    }
+
+    #[test]
+    fn iter_visits_internal_nodes_too() {
+      let tree = sample_tree();
+      assert_eq!(tree.iter(true).count(), 5); // 2 nodes + 3 leaves
+    }
+  }
 }
@@ -1,17 +1,55 @@
-use lib_genome_kit::amino::AminoAcid;
-use lib_genome_kit::blosum::Blosum;
+use lib_genome_kit::scoring::ScoringTable;
+
+/// Which predecessor cell produced a given `dp[i][j]`, i.e. which edit the
+/// alignment took to get there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+  /// Consume one residue from both `seq_a` and `seq_b`.
+  Diag,
+  /// Consume one residue from `seq_a`, gap in `seq_b`.
+  Up,
+  /// Consume one residue from `seq_b`, gap in `seq_a`.
+  Left,
+}
+
+/// A full global alignment: the optimal score plus the path that produced it,
+/// rendered out as two gap-padded rows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alignment<R> {
+  pub score: i32,
+  pub path: Vec<Step>,
+  pub aligned_a: Vec<Option<R>>,
+  pub aligned_b: Vec<Option<R>>,
+}
+
+/// The best local alignment between two sequences, i.e. the highest-scoring
+/// substring-to-substring match rather than a whole-sequence alignment.
+/// `start_a..end_a` and `start_b..end_b` are the (exclusive) coordinates of
+/// that substring in each original sequence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalAlignment<R> {
+  pub score: i32,
+  pub path: Vec<Step>,
+  pub aligned_a: Vec<Option<R>>,
+  pub aligned_b: Vec<Option<R>>,
+  pub start_a: usize,
+  pub end_a: usize,
+  pub start_b: usize,
+  pub end_b: usize,
+}
 
 /// We invert the original recursive problem, starting with the base cases (gaps)
 /// in the lowest levels (first row/col) and work our way up from smaller solutions
-/// to a bigger ones
+/// to a bigger ones. Generic over any residue `R` with a scoring `table`, so the
+/// same fill works for amino acids, nucleotides, or anything else scorable.
 ///
 /// - https://www.cs.otago.ac.nz/cosc348/alignments/Lecture05_GlobalAlignment.pdf
 /// - https://www.ncbi.nlm.nih.gov/nuccore/NC_050012.1?report=fasta
-fn align_dp_table(
-  seq_a: &[AminoAcid],
-  seq_b: &[AminoAcid],
+fn align_dp_table<R: Copy>(
+  seq_a: &[R],
+  seq_b: &[R],
   gap_penalty: i32,
-  table: &impl Blosum,
+  table: &impl ScoringTable<R>,
 ) -> i32 {
   let m = seq_a.len();
   let n = seq_b.len();
@@ -50,29 +88,292 @@ fn align_dp_table(
   dp[idx(m, n)]
 }
 
-/// We want to be able to run the needleman-wunsch algorithm on any of the tables,
-/// and blosum tables are all kinda the same, so we just default implement it...
-pub trait Needleman {
-  fn needleman_wunsch(seq_a: &[AminoAcid], seq_b: &[AminoAcid]) -> i32;
+/// Same recurrence as `align_dp_table`, but alongside the score matrix we keep
+/// a traceback matrix recording which predecessor produced each `dp[i][j]`, so
+/// the optimal path can be walked back afterwards. Ties are broken with a
+/// fixed priority: `Diag` over `Up` over `Left`.
+fn align_dp_table_with_traceback<R: Copy>(
+  seq_a: &[R],
+  seq_b: &[R],
+  gap_penalty: i32,
+  table: &impl ScoringTable<R>,
+) -> (i32, Vec<Step>) {
+  let m = seq_a.len();
+  let n = seq_b.len();
+
+  let mut dp = vec![0; (m + 1) * (n + 1)];
+  let mut back = vec![Step::Diag; (m + 1) * (n + 1)];
+  let idx = |i: usize, j: usize| i * (n + 1) + j;
+
+  for i in 1..=m {
+    dp[idx(i, 0)] = (i as i32) * gap_penalty;
+    back[idx(i, 0)] = Step::Up;
+  }
+  for j in 1..=n {
+    dp[idx(0, j)] = (j as i32) * gap_penalty;
+    back[idx(0, j)] = Step::Left;
+  }
+
+  for i in 1..=m {
+    for j in 1..=n {
+      let score = table.score(seq_a[i - 1], seq_b[j - 1]);
+      let diag = dp[idx(i - 1, j - 1)] + score;
+      let up = dp[idx(i - 1, j)] + gap_penalty;
+      let left = dp[idx(i, j - 1)] + gap_penalty;
+
+      // fixed tie-break priority: diag, then up, then left
+      let (best, step) = if diag >= up && diag >= left {
+        (diag, Step::Diag)
+      } else if up >= left {
+        (up, Step::Up)
+      } else {
+        (left, Step::Left)
+      };
+      dp[idx(i, j)] = best;
+      back[idx(i, j)] = step;
+    }
+  }
+
+  // walk back from (m,n) to (0,0), then reverse to read it forwards
+  let mut path = Vec::with_capacity(m + n);
+  let (mut i, mut j) = (m, n);
+  while i > 0 || j > 0 {
+    let step = back[idx(i, j)];
+    match step {
+      Step::Diag => {
+        i -= 1;
+        j -= 1;
+      }
+      Step::Up => i -= 1,
+      Step::Left => j -= 1,
+    }
+    path.push(step);
+  }
+  path.reverse();
+
+  (dp[idx(m, n)], path)
+}
+
+// turns a traceback path into the two gap-padded rows it describes...
+fn render_alignment<R: Copy>(seq_a: &[R], seq_b: &[R], path: &[Step]) -> (Vec<Option<R>>, Vec<Option<R>>) {
+  let mut aligned_a = Vec::with_capacity(path.len());
+  let mut aligned_b = Vec::with_capacity(path.len());
+  let (mut i, mut j) = (0, 0);
+  for &step in path {
+    match step {
+      Step::Diag => {
+        aligned_a.push(Some(seq_a[i]));
+        aligned_b.push(Some(seq_b[j]));
+        i += 1;
+        j += 1;
+      }
+      Step::Up => {
+        aligned_a.push(Some(seq_a[i]));
+        aligned_b.push(None);
+        i += 1;
+      }
+      Step::Left => {
+        aligned_a.push(None);
+        aligned_b.push(Some(seq_b[j]));
+        j += 1;
+      }
+    }
+  }
+  (aligned_a, aligned_b)
+}
+
+/// Gotoh's affine-gap recurrence over three score matrices: `m_mat` ends a
+/// match/mismatch, `ix_mat` ends a gap in `seq_b` (consuming `seq_a`), and
+/// `iy_mat` ends a gap in `seq_a` (consuming `seq_b`). `open` is charged on
+/// the first gap of a run, `extend` on every gap after that.
+///
+/// - https://doi.org/10.1016/0022-2836(82)90398-9
+fn align_dp_table_affine<R: Copy>(
+  seq_a: &[R],
+  seq_b: &[R],
+  open: i32,
+  extend: i32,
+  table: &impl ScoringTable<R>,
+) -> i32 {
+  let m = seq_a.len();
+  let n = seq_b.len();
+
+  const NEG_INF: i32 = i32::MIN / 2;
+
+  let mut m_mat = vec![NEG_INF; (m + 1) * (n + 1)];
+  let mut ix_mat = vec![NEG_INF; (m + 1) * (n + 1)];
+  let mut iy_mat = vec![NEG_INF; (m + 1) * (n + 1)];
+  let idx = |i: usize, j: usize| i * (n + 1) + j;
+
+  m_mat[idx(0, 0)] = 0;
+
+  // first column: every cell is a run of gaps in seq_b (consuming seq_a)
+  for i in 1..=m {
+    ix_mat[idx(i, 0)] = open + (i as i32 - 1) * extend;
+  }
+  // first row: every cell is a run of gaps in seq_a (consuming seq_b)
+  for j in 1..=n {
+    iy_mat[idx(0, j)] = open + (j as i32 - 1) * extend;
+  }
+
+  for i in 1..=m {
+    for j in 1..=n {
+      let score = table.score(seq_a[i - 1], seq_b[j - 1]);
+      let best_prev = m_mat[idx(i - 1, j - 1)]
+        .max(ix_mat[idx(i - 1, j - 1)])
+        .max(iy_mat[idx(i - 1, j - 1)]);
+      m_mat[idx(i, j)] = best_prev + score;
+
+      ix_mat[idx(i, j)] = (m_mat[idx(i - 1, j)] + open).max(ix_mat[idx(i - 1, j)] + extend);
+      iy_mat[idx(i, j)] = (m_mat[idx(i, j - 1)] + open).max(iy_mat[idx(i, j - 1)] + extend);
+    }
+  }
+
+  m_mat[idx(m, n)].max(ix_mat[idx(m, n)]).max(iy_mat[idx(m, n)])
+}
+
+/// Smith-Waterman local alignment: identical fill to `align_dp_table_with_traceback`
+/// except every cell is clamped to be at least 0, so a run of bad matches/gaps
+/// simply resets to a fresh start instead of dragging the whole alignment down.
+/// We track the best cell seen during the fill, then trace back from there
+/// until we hit a 0, which marks where the local alignment begins.
+///
+/// - https://doi.org/10.1016/0022-2836(81)90087-5
+fn local_align_dp_table_with_traceback<R: Copy>(
+  seq_a: &[R],
+  seq_b: &[R],
+  gap_penalty: i32,
+  table: &impl ScoringTable<R>,
+) -> (i32, usize, usize, Vec<Step>) {
+  let m = seq_a.len();
+  let n = seq_b.len();
+
+  let mut dp = vec![0; (m + 1) * (n + 1)];
+  let mut back = vec![Step::Diag; (m + 1) * (n + 1)];
+  let idx = |i: usize, j: usize| i * (n + 1) + j;
+
+  let mut best = 0;
+  let (mut best_i, mut best_j) = (0, 0);
+
+  for i in 1..=m {
+    for j in 1..=n {
+      let score = table.score(seq_a[i - 1], seq_b[j - 1]);
+      let diag = dp[idx(i - 1, j - 1)] + score;
+      let up = dp[idx(i - 1, j)] + gap_penalty;
+      let left = dp[idx(i, j - 1)] + gap_penalty;
+
+      // fixed tie-break priority: diag, then up, then left, then the 0 floor
+      let (cell, step) = if diag >= up && diag >= left && diag >= 0 {
+        (diag, Step::Diag)
+      } else if up >= left && up >= 0 {
+        (up, Step::Up)
+      } else if left >= 0 {
+        (left, Step::Left)
+      } else {
+        (0, Step::Diag)
+      };
+      dp[idx(i, j)] = cell;
+      back[idx(i, j)] = step;
+
+      if cell > best {
+        best = cell;
+        best_i = i;
+        best_j = j;
+      }
+    }
+  }
+
+  // walk back from the best cell until we hit a 0, which is where the local
+  // alignment starts
+  let mut path = Vec::new();
+  let (mut i, mut j) = (best_i, best_j);
+  while i > 0 && j > 0 && dp[idx(i, j)] > 0 {
+    let step = back[idx(i, j)];
+    match step {
+      Step::Diag => {
+        i -= 1;
+        j -= 1;
+      }
+      Step::Up => i -= 1,
+      Step::Left => j -= 1,
+    }
+    path.push(step);
+  }
+  path.reverse();
+
+  (best, i, j, path)
+}
+
+/// We want to be able to run the needleman-wunsch algorithm on any residue
+/// with a scoring table, and scoring tables are all kinda the same, so we
+/// just default implement it...
+pub trait Needleman<R> {
+  fn needleman_wunsch(seq_a: &[R], seq_b: &[R]) -> i32;
+
+  /// Like `needleman_wunsch`, but returns the full optimal alignment instead
+  /// of just the score.
+  fn align(seq_a: &[R], seq_b: &[R]) -> Alignment<R>;
+
+  /// Global alignment under Gotoh's affine-gap model: `open` is charged once
+  /// per gap run and `extend` for every residue after that, which penalizes
+  /// one long indel less harshly than the same number of scattered gaps.
+  fn needleman_wunsch_affine(seq_a: &[R], seq_b: &[R], open: i32, extend: i32) -> i32;
+
+  /// Best local alignment between `seq_a` and `seq_b`, useful when only a
+  /// conserved core is shared and a global alignment would dilute that signal.
+  fn smith_waterman(seq_a: &[R], seq_b: &[R]) -> LocalAlignment<R>;
 }
 
 // blanket impl that actually gives tables access to the needleman algorithm
-impl<B> Needleman for B 
-where 
-  B: Blosum + Default
+impl<R, S> Needleman<R> for S
+where
+  R: Copy,
+  S: ScoringTable<R> + Default,
 {
   #[inline]
-  fn needleman_wunsch(seq_a: &[AminoAcid], seq_b: &[AminoAcid]) -> i32 {
+  fn needleman_wunsch(seq_a: &[R], seq_b: &[R]) -> i32 {
     align_dp_table(seq_a, seq_b, -5, &Self::default())
   }
+
+  fn align(seq_a: &[R], seq_b: &[R]) -> Alignment<R> {
+    let (score, path) = align_dp_table_with_traceback(seq_a, seq_b, -5, &Self::default());
+    let (aligned_a, aligned_b) = render_alignment(seq_a, seq_b, &path);
+    Alignment {
+      score,
+      path,
+      aligned_a,
+      aligned_b,
+    }
+  }
+
+  #[inline]
+  fn needleman_wunsch_affine(seq_a: &[R], seq_b: &[R], open: i32, extend: i32) -> i32 {
+    align_dp_table_affine(seq_a, seq_b, open, extend, &Self::default())
+  }
+
+  fn smith_waterman(seq_a: &[R], seq_b: &[R]) -> LocalAlignment<R> {
+    let (score, start_a, start_b, path) =
+      local_align_dp_table_with_traceback(seq_a, seq_b, -5, &Self::default());
+    let (aligned_a, aligned_b) = render_alignment(&seq_a[start_a..], &seq_b[start_b..], &path);
+    LocalAlignment {
+      score,
+      end_a: start_a + aligned_a.iter().filter(|a| a.is_some()).count(),
+      end_b: start_b + aligned_b.iter().filter(|b| b.is_some()).count(),
+      start_a,
+      start_b,
+      path,
+      aligned_a,
+      aligned_b,
+    }
+  }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
 
+  use lib_genome_kit::amino::AminoAcid;
   use lib_genome_kit::genome::Genome;
-  // we only test with `Blosum62` as of now...
   use lib_genome_kit::blosum::Blosum62;
 
   mod needleman {
@@ -80,8 +381,8 @@ mod tests {
 
     #[test]
     fn needleman_wunsch_empty_sequences() {
-      let seq1 = Genome::from("".chars());
-      let seq2 = Genome::from("".chars());
+      let seq1: Genome<AminoAcid> = Genome::from("".chars());
+      let seq2: Genome<AminoAcid> = Genome::from("".chars());
       // both empty, 0 * gap = 0
       let score = Blosum62::needleman_wunsch(&seq1, &seq2);
       assert_eq!(score, 0);
@@ -89,8 +390,8 @@ mod tests {
 
     #[test]
     fn needleman_wunsch_one_empty_sequence() {
-      let seq1 = Genome::from("".chars());
-      let seq2 = Genome::from("ARN".chars());
+      let seq1: Genome<AminoAcid> = Genome::from("".chars());
+      let seq2: Genome<AminoAcid> = Genome::from("ARN".chars());
       // if a sequence is empty, we run the gap for the rest of the opposing
       // sequence (3 chars * -5 gap)
       let score = Blosum62::needleman_wunsch(&seq1, &seq2);
@@ -99,8 +400,8 @@ mod tests {
 
     #[test]
     fn needleman_wunsch_single_char_match() {
-      let seq1 = Genome::from("A".chars());
-      let seq2 = Genome::from("A".chars());
+      let seq1: Genome<AminoAcid> = Genome::from("A".chars());
+      let seq2: Genome<AminoAcid> = Genome::from("A".chars());
       // score of 4 for A-A match
       let score = Blosum62::needleman_wunsch(&seq1, &seq2);
       assert_eq!(score, 4);
@@ -108,8 +409,8 @@ mod tests {
 
     #[test]
     fn needleman_wunsch_single_char_mismatch() {
-      let seq1 = Genome::from("A".chars());
-      let seq2 = Genome::from("R".chars());
+      let seq1: Genome<AminoAcid> = Genome::from("A".chars());
+      let seq2: Genome<AminoAcid> = Genome::from("R".chars());
       // A-R alignment score (-1) better than gap (-5)
       let score = Blosum62::needleman_wunsch(&seq1, &seq2);
       assert_eq!(score, -1);
@@ -117,10 +418,146 @@ mod tests {
 
     #[test]
     fn needleman_wunsch_longer_sequence() {
-      let seq1 = Genome::from("PLEASANTLY".chars());
-      let seq2 = Genome::from("MEANLY".chars());
+      let seq1: Genome<AminoAcid> = Genome::from("PLEASANTLY".chars());
+      let seq2: Genome<AminoAcid> = Genome::from("MEANLY".chars());
       let score = Blosum62::needleman_wunsch(&seq1, &seq2);
       assert_eq!(score, 8);
     }
   }
+
+  mod align {
+    use super::*;
+
+    #[test]
+    fn align_matches_needleman_wunsch_score() {
+      let seq1: Genome<AminoAcid> = Genome::from("PLEASANTLY".chars());
+      let seq2: Genome<AminoAcid> = Genome::from("MEANLY".chars());
+      let alignment = Blosum62::align(&seq1, &seq2);
+      assert_eq!(alignment.score, Blosum62::needleman_wunsch(&seq1, &seq2));
+    }
+
+    #[test]
+    fn align_single_char_match_has_one_diag_step() {
+      let seq1: Genome<AminoAcid> = Genome::from("A".chars());
+      let seq2: Genome<AminoAcid> = Genome::from("A".chars());
+      let alignment = Blosum62::align(&seq1, &seq2);
+      assert_eq!(alignment.path, vec![Step::Diag]);
+      assert_eq!(alignment.aligned_a, vec![Some(AminoAcid::Alanine)]);
+      assert_eq!(alignment.aligned_b, vec![Some(AminoAcid::Alanine)]);
+    }
+
+    #[test]
+    fn align_one_empty_sequence_is_all_gaps() {
+      let seq1: Genome<AminoAcid> = Genome::from("".chars());
+      let seq2: Genome<AminoAcid> = Genome::from("ARN".chars());
+      let alignment = Blosum62::align(&seq1, &seq2);
+      assert_eq!(alignment.path, vec![Step::Left, Step::Left, Step::Left]);
+      assert_eq!(alignment.aligned_a, vec![None, None, None]);
+      assert_eq!(
+        alignment.aligned_b,
+        vec![
+          Some(AminoAcid::Alanine),
+          Some(AminoAcid::Arginine),
+          Some(AminoAcid::Asparagine)
+        ]
+      );
+    }
+  }
+
+  mod needleman_wunsch_affine {
+    use super::*;
+
+    #[test]
+    fn affine_empty_sequences() {
+      let seq1: Genome<AminoAcid> = Genome::from("".chars());
+      let seq2: Genome<AminoAcid> = Genome::from("".chars());
+      let score = Blosum62::needleman_wunsch_affine(&seq1, &seq2, -11, -1);
+      assert_eq!(score, 0);
+    }
+
+    #[test]
+    fn affine_single_char_match() {
+      let seq1: Genome<AminoAcid> = Genome::from("A".chars());
+      let seq2: Genome<AminoAcid> = Genome::from("A".chars());
+      let score = Blosum62::needleman_wunsch_affine(&seq1, &seq2, -11, -1);
+      assert_eq!(score, 4);
+    }
+
+    #[test]
+    fn affine_one_empty_sequence_charges_a_single_gap_open() {
+      let seq1: Genome<AminoAcid> = Genome::from("".chars());
+      let seq2: Genome<AminoAcid> = Genome::from("ARN".chars());
+      // one gap run of length 3: open + 2*extend
+      let score = Blosum62::needleman_wunsch_affine(&seq1, &seq2, -11, -1);
+      assert_eq!(score, -11 + 2 * -1);
+    }
+
+    #[test]
+    fn affine_scores_at_least_as_well_as_linear_gap() {
+      let seq1: Genome<AminoAcid> = Genome::from("PLEASANTLY".chars());
+      let seq2: Genome<AminoAcid> = Genome::from("MEANLY".chars());
+      let linear = Blosum62::needleman_wunsch(&seq1, &seq2);
+      let affine = Blosum62::needleman_wunsch_affine(&seq1, &seq2, -11, -1);
+      assert!(affine >= linear);
+    }
+  }
+
+  mod smith_waterman {
+    use super::*;
+
+    #[test]
+    fn local_score_is_never_negative() {
+      let seq1: Genome<AminoAcid> = Genome::from("".chars());
+      let seq2: Genome<AminoAcid> = Genome::from("".chars());
+      let alignment = Blosum62::smith_waterman(&seq1, &seq2);
+      assert_eq!(alignment.score, 0);
+    }
+
+    #[test]
+    fn local_score_at_least_as_good_as_global() {
+      let seq1: Genome<AminoAcid> = Genome::from("PLEASANTLY".chars());
+      let seq2: Genome<AminoAcid> = Genome::from("MEANLY".chars());
+      let global = Blosum62::needleman_wunsch(&seq1, &seq2);
+      let local = Blosum62::smith_waterman(&seq1, &seq2);
+      assert!(local.score >= global);
+    }
+
+    #[test]
+    fn local_alignment_finds_shared_core() {
+      // a conserved "ARND" core flanked by unrelated junk on both sides
+      let seq1: Genome<AminoAcid> = Genome::from("WWWARNDWWW".chars());
+      let seq2: Genome<AminoAcid> = Genome::from("YYARNDYY".chars());
+      let alignment = Blosum62::smith_waterman(&seq1, &seq2);
+      assert!(alignment.score > 0);
+      assert!(alignment.start_a <= alignment.end_a);
+      assert!(alignment.end_a <= seq1.len());
+      assert!(alignment.start_b <= alignment.end_b);
+      assert!(alignment.end_b <= seq2.len());
+    }
+  }
+
+  mod nucleotide {
+    use super::*;
+
+    use lib_genome_kit::nuc_matrix::Nuc44;
+    use lib_genome_kit::nucleotide::Nucleotide;
+
+    #[test]
+    fn needleman_wunsch_identical_sequences() {
+      let seq1: Genome<Nucleotide> = Genome::from("ACGT".chars());
+      let seq2: Genome<Nucleotide> = Genome::from("ACGT".chars());
+      // 4 matches at +5 each
+      let score = Nuc44::needleman_wunsch(&seq1, &seq2);
+      assert_eq!(score, 20);
+    }
+
+    #[test]
+    fn needleman_wunsch_single_mismatch() {
+      let seq1: Genome<Nucleotide> = Genome::from("ACGT".chars());
+      let seq2: Genome<Nucleotide> = Genome::from("ACGA".chars());
+      // 3 matches at +5 and 1 mismatch at -4
+      let score = Nuc44::needleman_wunsch(&seq1, &seq2);
+      assert_eq!(score, 11);
+    }
+  }
 }
@@ -5,10 +5,24 @@ use std::path::Path;
 
 use anyhow::Context;
 
+use lib_genome_kit::amino::AminoAcid;
+use lib_genome_kit::blosum::Blosum62;
 use lib_kruskal::{Cluster, Kruskal, Species};
 
-// we will be anaylzing COX3 (Cytochrome c oxidase subunit III)
-fn load_species_from_file(path: impl AsRef<Path>) -> Result<Vec<Species>, anyhow::Error> {
+// we will be anaylzing COX3 (Cytochrome c oxidase subunit III), so all of our
+// species are amino acid genomes...
+
+// dispatches on file extension so we can ingest either our own flat JSON map
+// or FASTA straight from NCBI without a preprocessing step...
+fn load_species_from_file(path: impl AsRef<Path>) -> Result<Vec<Species<AminoAcid>>, anyhow::Error> {
+  let path = path.as_ref();
+  match path.extension().and_then(|ext| ext.to_str()) {
+    Some("fasta") | Some("fa") | Some("faa") => load_species_from_fasta(path),
+    _ => load_species_from_json(path),
+  }
+}
+
+fn load_species_from_json(path: impl AsRef<Path>) -> Result<Vec<Species<AminoAcid>>, anyhow::Error> {
   let json_str = fs::read_to_string(path)?;
   let data: HashMap<String, String> = serde_json::from_str(&json_str)?;
   let species = data
@@ -18,13 +32,62 @@ fn load_species_from_file(path: impl AsRef<Path>) -> Result<Vec<Species>, anyhow
   Ok(species)
 }
 
+fn load_species_from_fasta(path: impl AsRef<Path>) -> Result<Vec<Species<AminoAcid>>, anyhow::Error> {
+  let fasta_str = fs::read_to_string(path)?;
+  Ok(parse_fasta(&fasta_str))
+}
+
+// parses `>header\nSEQUENCE...` records, where the sequence may be wrapped
+// across multiple lines and a file may hold multiple records; the species
+// name is taken as the first whitespace-delimited token of the header...
+fn parse_fasta(fasta_str: &str) -> Vec<Species<AminoAcid>> {
+  let mut species = Vec::new();
+  let mut current_name: Option<String> = None;
+  let mut current_seq = String::new();
+
+  for line in fasta_str.lines() {
+    let line = line.trim();
+    if line.is_empty() {
+      continue;
+    }
+
+    if let Some(header) = line.strip_prefix('>') {
+      if let Some(name) = current_name.take() {
+        species.push(Species::new(name, current_seq.chars()));
+      }
+      // also drops any sequence data that appeared before the first header
+      current_seq.clear();
+      let name = header.split_whitespace().next().unwrap_or(header);
+      current_name = Some(name.to_string());
+    } else {
+      current_seq.push_str(line);
+    }
+  }
+
+  if let Some(name) = current_name.take() {
+    species.push(Species::new(name, current_seq.chars()));
+  }
+
+  species
+}
+
 fn emit_graphviz<T: Display>(cluster: Cluster<T>) -> String {
-  // we need to create some nodes with some ids, so we better keep track of what
-  // id we are on...
-  fn traverse<T: Display>(cluster: &Cluster<T>, counter: &mut usize, output: &mut String) -> usize {
-    let node_id = *counter;
-    *counter += 1;
-    match cluster {
+  // assign each node a stable id in the same pre-order `Cluster::iter` walks,
+  // keyed by pointer identity so a `Node`'s `left`/`right` can be looked back
+  // up by address once we get to emitting its edges...
+  let node_ids: HashMap<*const Cluster<T>, usize> = cluster
+    .iter(true)
+    .enumerate()
+    .map(|(id, node)| (node as *const Cluster<T>, id))
+    .collect();
+
+  let mut output = String::new();
+  output.push_str("digraph ClusterTree {\n");
+  output.push_str("  node [fontname=\"Helvetica\"];\n");
+
+  for node in cluster.iter(true) {
+    let node_id = node_ids[&(node as *const Cluster<T>)];
+    match node {
       Cluster::Leaf(val) => {
         // our leaves have a box shape to distinguish them
         output.push_str(&format!(
@@ -37,32 +100,23 @@ fn emit_graphviz<T: Display>(cluster: Cluster<T>) -> String {
         right,
         similarity,
       } => {
-        // define an internal node for merge similarity
+        // define an internal node for the merge value; what this number means
+        // depends on how the tree was built (see `Cluster`'s doc comment), so
+        // we label it generically rather than implying it's always a raw
+        // similarity score...
         output.push_str(&format!(
-          "  node{} [label=\"sim: {}\"];\n",
+          "  node{} [label=\"merge: {:.3}\"];\n",
           node_id, similarity
         ));
-        // find kids
-        let left_id = traverse(left, counter, output);
-        let right_id = traverse(right, counter, output);
         // connect parent to kids
+        let left_id = node_ids[&(left.as_ref() as *const Cluster<T>)];
+        let right_id = node_ids[&(right.as_ref() as *const Cluster<T>)];
         output.push_str(&format!("  node{} -> node{};\n", node_id, left_id));
         output.push_str(&format!("  node{} -> node{};\n", node_id, right_id));
       }
     }
-    node_id
   }
 
-  // count unique node ids
-  let mut counter = 0;
-
-  let mut output = String::new();
-  output.push_str("digraph ClusterTree {\n");
-  output.push_str("  node [fontname=\"Helvetica\"];\n");
-
-  // traverse from root
-  traverse(&cluster, &mut counter, &mut output);
-
   output.push_str("}\n");
   output
 }
@@ -74,11 +128,188 @@ fn write_graph_to_file(graph_dot: String) -> Result<(), anyhow::Error> {
   Ok(())
 }
 
+// converts a merge value into a tree height: `Cluster::Node.similarity` is
+// the negated merge height, built from a normalized pairwise distance (see
+// `lib_kruskal`'s `normalized_distance`; `Kruskal` stores the distance itself,
+// `Upgma` half of it), so negating it back recovers that height directly. The clamp only guards against the rare case of a
+// normalized distance dipping below zero; it's not expected to fire for
+// realistic data the way it would for a raw, unnormalized alignment score...
+fn similarity_to_height(similarity: f64) -> f64 {
+  (-similarity).max(0.0)
+}
+
+// Newick reserves whitespace and a handful of punctuation characters for
+// structure, so any taxon name containing them has to be single-quoted, with
+// internal quotes doubled per the spec...
+fn escape_newick_name(name: &str) -> String {
+  let needs_quoting = name
+    .chars()
+    .any(|c| c.is_whitespace() || matches!(c, ',' | '(' | ')' | ':' | ';' | '\''));
+  if needs_quoting {
+    format!("'{}'", name.replace('\'', "''"))
+  } else {
+    name.to_string()
+  }
+}
+
+fn emit_newick<T: Display>(cluster: &Cluster<T>) -> String {
+  // returns this node's own height so the caller can compute its branch length
+  fn traverse<T: Display>(cluster: &Cluster<T>, output: &mut String) -> f64 {
+    match cluster {
+      Cluster::Leaf(name) => {
+        output.push_str(&escape_newick_name(&name.to_string()));
+        0.0
+      }
+      Cluster::Node {
+        left,
+        right,
+        similarity,
+      } => {
+        let height = similarity_to_height(*similarity);
+
+        output.push('(');
+        let left_height = traverse(left, output);
+        output.push_str(&format!(":{:.6}", height - left_height));
+        output.push(',');
+        let right_height = traverse(right, output);
+        output.push_str(&format!(":{:.6}", height - right_height));
+        output.push(')');
+
+        height
+      }
+    }
+  }
+
+  let mut output = String::new();
+  traverse(cluster, &mut output);
+  output.push(';');
+  output
+}
+
+fn write_newick_to_file(newick: String) -> Result<(), anyhow::Error> {
+  fs::write("phylogeny.nwk", newick)?;
+  Ok(())
+}
+
 fn main() -> Result<(), anyhow::Error> {
   let species = load_species_from_file("pull-species/cox3_translations.json")?;
   // the root `Cluster` represents a dendrogram with all species...
-  let dendrogram = species.cluster().context("no graph found")?;
-  let graph = emit_graphviz(dendrogram);
+  let dendrogram = Kruskal::<AminoAcid, Blosum62>::cluster(species).context("no graph found")?;
+
+  let graph = emit_graphviz(dendrogram.clone());
   write_graph_to_file(graph)?;
+
+  let newick = emit_newick(&dendrogram);
+  write_newick_to_file(newick)?;
+
   Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  mod escape_newick_name {
+    use super::*;
+
+    #[test]
+    fn plain_name_is_left_untouched() {
+      assert_eq!(escape_newick_name("HomoSapiens"), "HomoSapiens");
+    }
+
+    #[test]
+    fn whitespace_triggers_quoting() {
+      assert_eq!(escape_newick_name("Homo sapiens"), "'Homo sapiens'");
+    }
+
+    #[test]
+    fn embedded_single_quote_round_trips() {
+      // the spec doubles internal quotes, so a name with one literal `'`
+      // should come back out with it doubled between the wrapping quotes...
+      let escaped = escape_newick_name("O'Brien's species");
+      assert_eq!(escaped, "'O''Brien''s species'");
+      // and replacing the doubled quotes back down to single ones recovers
+      // the original name, i.e. this is a real round trip, not just doubling
+      let unquoted = &escaped[1..escaped.len() - 1];
+      assert_eq!(unquoted.replace("''", "'"), "O'Brien's species");
+    }
+
+    #[test]
+    fn reserved_punctuation_triggers_quoting() {
+      assert_eq!(escape_newick_name("a;b"), "'a;b'");
+      assert_eq!(escape_newick_name("a,b"), "'a,b'");
+      assert_eq!(escape_newick_name("a(b)"), "'a(b)'");
+      assert_eq!(escape_newick_name("a:b"), "'a:b'");
+    }
+  }
+
+  mod similarity_to_height {
+    use super::*;
+
+    #[test]
+    fn negative_similarity_becomes_positive_height() {
+      assert_eq!(similarity_to_height(-4.5), 4.5);
+    }
+
+    #[test]
+    fn positive_similarity_clamps_to_zero() {
+      // a positive merge value would otherwise produce a negative height,
+      // which doesn't make sense for a tree rooted at the leaves...
+      assert_eq!(similarity_to_height(4.5), 0.0);
+    }
+
+    #[test]
+    fn zero_similarity_is_zero_height() {
+      assert_eq!(similarity_to_height(0.0), 0.0);
+    }
+  }
+
+  mod parse_fasta {
+    use super::*;
+
+    #[test]
+    fn single_record_single_line() {
+      let fasta = ">SpeciesA\nARND\n";
+      let species = parse_fasta(fasta);
+      assert_eq!(species, vec![Species::new("SpeciesA".into(), "ARND".chars())]);
+    }
+
+    #[test]
+    fn sequence_wrapped_across_multiple_lines() {
+      let fasta = ">SpeciesA\nAR\nND\n";
+      let species = parse_fasta(fasta);
+      assert_eq!(species, vec![Species::new("SpeciesA".into(), "ARND".chars())]);
+    }
+
+    #[test]
+    fn multiple_records_are_all_parsed() {
+      let fasta = ">SpeciesA desc\nARND\n>SpeciesB desc\nRRDD\n";
+      let species = parse_fasta(fasta);
+      assert_eq!(
+        species,
+        vec![
+          Species::new("SpeciesA".into(), "ARND".chars()),
+          Species::new("SpeciesB".into(), "RRDD".chars()),
+        ]
+      );
+    }
+
+    #[test]
+    fn record_with_no_trailing_blank_line_is_still_captured() {
+      // the last record only gets flushed once we've hit end of input, so
+      // there's no next header to trigger it...
+      let fasta = ">SpeciesA\nARND";
+      let species = parse_fasta(fasta);
+      assert_eq!(species, vec![Species::new("SpeciesA".into(), "ARND".chars())]);
+    }
+
+    #[test]
+    fn sequence_data_before_any_header_is_discarded() {
+      // malformed input: there's no `current_name` yet to attach this
+      // sequence data to, so it's silently dropped rather than panicking...
+      let fasta = "ARND\n>SpeciesA\nRRDD\n";
+      let species = parse_fasta(fasta);
+      assert_eq!(species, vec![Species::new("SpeciesA".into(), "RRDD".chars())]);
+    }
+  }
+}